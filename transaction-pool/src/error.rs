@@ -0,0 +1,22 @@
+//! Transaction Pool errors.
+
+error_chain! {
+	errors {
+		/// Transaction is already imported into the pool.
+		AlreadyImported(hash: String) {
+			description("transaction is already in the pool")
+			display("[{}] already imported", hash)
+		}
+		/// The transaction's score is below the pool's minimal score, or the pool
+		/// is full and the transaction is too cheap to replace any existing one.
+		TooCheapToEnter(hash: String, min_score: String) {
+			description("the transaction is too cheap to enter the pool")
+			display("[{}] too cheap to enter the pool. Min score: {}", hash, min_score)
+		}
+		/// The transaction is too cheap to replace an existing one from the same sender.
+		TooCheapToReplace(old_hash: String, hash: String) {
+			description("the transaction is too cheap to replace an existing transaction in the pool")
+			display("[{}] too cheap to replace: {}", hash, old_hash)
+		}
+	}
+}
@@ -7,91 +7,73 @@ extern crate log;
 
 mod error;
 mod pool;
+mod scoring;
 
-pub use self::pool::Pool;
+pub use self::error::{Error, ErrorKind};
+pub use self::pool::{Pool, PendingIterator};
+pub use self::scoring::{NonceAndGasPrice, ScoredTransaction};
 
 use std::sync::Arc;
+use std::hash::Hash;
+use std::ops::Deref;
 use std::{cmp, fmt};
 
-// Types
-#[derive(Debug)]
-pub struct UnverifiedTransaction;
-#[derive(Debug)]
-pub struct SignedTransaction;
-#[derive(Debug, PartialEq)]
-pub struct VerifiedTransaction {
-	pub hash: H256,
-	pub nonce: U256,
-	pub gas_price: U256,
-	pub gas: U256,
-	pub sender: Address,
-	pub insertion_id: u64,
-}
-impl VerifiedTransaction {
-	pub fn hash(&self) -> H256 {
-		self.hash.clone()
-	}
-
-	pub fn mem_usage(&self) -> usize {
-		self.nonce.0 as usize
-	}
-
-	pub fn sender(&self) -> Address {
-		self.sender.clone()
-	}
-}
-#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Address(u64);
-impl From<u64> for Address {
-	fn from(x: u64) -> Self {
-		Address(x)
-	}
+/// Transaction as accepted by the pool.
+///
+/// The pool is generic over the transaction representation so that downstream
+/// crates can plug in their own hash/sender types (bigint hashes, non-Ethereum
+/// chains) without forking the pool. Everything the pool needs to know about a
+/// transaction is expressed through this trait.
+pub trait VerifiedTransaction: fmt::Debug {
+	/// Transaction hash type.
+	type Hash: Clone + Eq + Hash + fmt::Debug;
+	/// Transaction sender type.
+	type Sender: Clone + Eq + Hash;
+
+	/// Transaction hash.
+	fn hash(&self) -> &Self::Hash;
+
+	/// Transaction sender.
+	fn sender(&self) -> &Self::Sender;
+
+	/// Memory usage of this transaction in bytes.
+	fn mem_usage(&self) -> usize;
+
+	/// Pool-wide insertion id.
+	///
+	/// Unique, monotonically increasing id assigned to the transaction when it
+	/// is first seen. Scoring uses it to break ties by arrival order and the
+	/// pool uses it to reclaim stale entries.
+	fn insertion_id(&self) -> u64;
 }
 
-#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct U256(u64);
-impl From<u64> for U256 {
-	fn from(x: u64) -> Self {
-		U256(x)
-	}
-}
-#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct H256(u64);
-impl From<u64> for H256 {
-	fn from(x: u64) -> Self {
-		H256(x)
-	}
-}
-
-pub type SharedTransaction = Arc<VerifiedTransaction>;
+/// A shared, reference-counted transaction stored in the pool.
+pub type SharedTransaction<T> = Arc<T>;
 
 /// Main part of the transaction verification is decoupled from the pool
 pub trait Verifier {
 	type Error;
+	type VerifiedTransaction: VerifiedTransaction;
 
-	fn verify_transaction(&self, tx: UnverifiedTransaction) -> Result<VerifiedTransaction, Self::Error>;
+	fn verify_transaction(&self, tx: UnverifiedTransaction) -> Result<Self::VerifiedTransaction, Self::Error>;
 }
 
-pub struct NoopVerifier;
-impl Verifier for NoopVerifier {
-	type Error = ();
-
-	fn verify_transaction(&self, _tx: UnverifiedTransaction) -> Result<VerifiedTransaction, Self::Error> {
-		unimplemented!()
-	}
-}
+#[derive(Debug)]
+pub struct UnverifiedTransaction;
+#[derive(Debug)]
+pub struct SignedTransaction;
 
 // TODO [ToDr] Should accept SharedTransaction instead.
-pub trait Listener {
-	fn added(&mut self, _tx: &VerifiedTransaction, _old: Option<&VerifiedTransaction>) {}
-	fn rejected(&mut self, _tx: &VerifiedTransaction) {}
-	fn dropped(&mut self, _tx: &VerifiedTransaction) {}
-	fn invalid(&mut self, _tx: &VerifiedTransaction) {}
-	fn cancelled(&mut self, _tx: &VerifiedTransaction) {}
+pub trait Listener<T: VerifiedTransaction> {
+	fn added(&mut self, _tx: &SharedTransaction<T>, _old: Option<&SharedTransaction<T>>) {}
+	fn rejected(&mut self, _tx: &SharedTransaction<T>) {}
+	fn dropped(&mut self, _tx: &SharedTransaction<T>) {}
+	fn invalid(&mut self, _tx: &SharedTransaction<T>) {}
+	fn cancelled(&mut self, _tx: &SharedTransaction<T>) {}
 }
 
 pub struct NoopListener;
-impl Listener for NoopListener {}
+impl<T: VerifiedTransaction> Listener<T> for NoopListener {}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScoringChoice {
@@ -116,16 +98,57 @@ pub enum ScoringChange {
 	ReplacedAt(usize),
 }
 
-pub trait Scoring {
+pub trait Scoring<T: VerifiedTransaction> {
 	type Score: cmp::Ord + Clone + Default + fmt::Debug;
 
-	fn compare(&self, old: &VerifiedTransaction, other: &VerifiedTransaction) -> cmp::Ordering;
+	fn compare(&self, old: &T, other: &T) -> cmp::Ordering;
 
-	fn choose(&self, old: &VerifiedTransaction, new: &VerifiedTransaction) -> ScoringChoice;
+	fn choose(&self, old: &T, new: &T) -> ScoringChoice;
 
-	fn update_scores(&self, txs: &[SharedTransaction], scores: &mut [Self::Score], change: ScoringChange);
+	fn update_scores(&self, txs: &[SharedTransaction<T>], scores: &mut [Self::Score], change: ScoringChange);
+}
+
+/// A candidate transaction together with a view of what its sender already has queued.
+///
+/// Eviction policies need more than the two transactions being compared: a new
+/// transaction from a sender who already has transactions in the pool must not
+/// evict the pool's global worst if that would strand a higher-nonce transaction
+/// from the same sender. Exposing the sender's existing set lets the policy fall
+/// back to natural priority ordering when the senders differ.
+pub struct ReplaceTransaction<'a, T: VerifiedTransaction + 'a> {
+	transaction: &'a SharedTransaction<T>,
+	pooled_by_sender: Option<&'a [SharedTransaction<T>]>,
+}
+
+impl<'a, T: VerifiedTransaction> ReplaceTransaction<'a, T> {
+	/// Create a new view over a candidate transaction.
+	pub fn new(transaction: &'a SharedTransaction<T>, pooled_by_sender: Option<&'a [SharedTransaction<T>]>) -> Self {
+		ReplaceTransaction {
+			transaction,
+			pooled_by_sender,
+		}
+	}
+
+	/// Transactions already queued for this transaction's sender, ordered by nonce.
+	pub fn pooled_by_sender(&self) -> &[SharedTransaction<T>] {
+		self.pooled_by_sender.unwrap_or(&[])
+	}
+}
+
+impl<'a, T: VerifiedTransaction> Deref for ReplaceTransaction<'a, T> {
+	type Target = SharedTransaction<T>;
+
+	fn deref(&self) -> &Self::Target {
+		self.transaction
+	}
+}
 
-	fn should_replace(&self, old: &VerifiedTransaction, new: &VerifiedTransaction) -> bool;
+/// Decides which transaction to evict when the pool is full.
+///
+/// Kept separate from `Scoring` because ordering within a sender and the
+/// "whom do I evict?" decision across senders are distinct concerns.
+pub trait ShouldReplace<T: VerifiedTransaction> {
+	fn should_replace(&self, old: &ReplaceTransaction<T>, new: &ReplaceTransaction<T>) -> ScoringChoice;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -141,14 +164,73 @@ impl From<bool> for Readiness {
 	}
 }
 
-pub trait Ready {
+pub trait Ready<T: VerifiedTransaction> {
 	/// Returns true if transaction is ready to be included in pending block,
 	/// given all previous transactions that were ready are included.
-	fn is_ready(&mut self, tx: &VerifiedTransaction) -> Readiness;
+	fn is_ready(&mut self, tx: &T) -> Readiness;
 }
 
-impl<F> Ready for F where F: FnMut(&VerifiedTransaction) -> Readiness {
-	fn is_ready(&mut self, tx: &VerifiedTransaction) -> Readiness {
+impl<T, F> Ready<T> for F where T: VerifiedTransaction, F: FnMut(&T) -> Readiness {
+	fn is_ready(&mut self, tx: &T) -> Readiness {
 		(*self)(tx)
 	}
 }
+
+/// Pool limits.
+///
+/// Generic over the scoring `Score` so the pool can reject transactions whose
+/// computed score falls below `minimal_score` without ever touching the data
+/// structures.
+#[derive(Debug, Clone)]
+pub struct Options<S = ()> {
+	/// Maximal number of transactions in the pool.
+	pub max_count: usize,
+	/// Maximal number of transactions from single sender.
+	pub max_per_sender: usize,
+	/// Maximal cumulative memory usage of the pool, in bytes.
+	pub max_mem_usage: usize,
+	/// Minimal score a transaction must reach to be accepted.
+	///
+	/// The default (`Score::default()`) imposes no floor.
+	pub minimal_score: S,
+}
+
+impl<S: Default> Default for Options<S> {
+	fn default() -> Self {
+		Options {
+			max_count: 1024,
+			max_per_sender: 16,
+			max_mem_usage: 8 * 1024 * 1024,
+			minimal_score: Default::default(),
+		}
+	}
+}
+
+/// An authoritative snapshot of the pool's size, with memory usage recomputed
+/// from the stored transactions (see [`Pool::status`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+	/// Number of transactions in the pool.
+	pub transaction_count: usize,
+	/// Number of distinct senders with queued transactions.
+	pub senders: usize,
+	/// Cumulative memory usage of the pool, in bytes.
+	pub mem_usage: usize,
+}
+
+/// A cheap snapshot of the pool's size, read straight from the running counters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightStatus {
+	/// Number of transactions in the pool.
+	pub transaction_count: usize,
+	/// Number of distinct senders with queued transactions.
+	pub senders: usize,
+	/// Cumulative memory usage of the pool, in bytes.
+	pub mem_usage: usize,
+}
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+pub use self::tests::{Transaction, Address, U256, H256};
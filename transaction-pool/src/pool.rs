@@ -0,0 +1,637 @@
+use std::cmp;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use error;
+use {
+	Listener, LightStatus, NoopListener, Options, Ready, Readiness, ReplaceTransaction, Scoring,
+	ScoringChange, ScoringChoice, SharedTransaction, ShouldReplace, Status, VerifiedTransaction,
+};
+
+/// A transaction together with its global score.
+type Scored<T, S> = (S, SharedTransaction<T>);
+/// The worst (highest-nonce) and best (lowest-nonce) scored transactions of a sender.
+type WorstAndBest<T, S> = (Scored<T, S>, Scored<T, S>);
+
+/// Transaction with a cached score, used to order senders globally.
+#[derive(Debug)]
+struct ScoreWithRef<T, S> {
+	score: S,
+	transaction: SharedTransaction<T>,
+}
+
+impl<T, S> ScoreWithRef<T, S> {
+	fn new(score: S, transaction: SharedTransaction<T>) -> Self {
+		ScoreWithRef { score, transaction }
+	}
+}
+
+impl<T: VerifiedTransaction, S: Clone> Clone for ScoreWithRef<T, S> {
+	fn clone(&self) -> Self {
+		ScoreWithRef {
+			score: self.score.clone(),
+			transaction: self.transaction.clone(),
+		}
+	}
+}
+
+impl<T: VerifiedTransaction, S: cmp::Ord> Ord for ScoreWithRef<T, S> {
+	fn cmp(&self, other: &Self) -> cmp::Ordering {
+		// Higher score comes first, earlier arrivals break ties.
+		other.score.cmp(&self.score)
+			.then(self.transaction.insertion_id().cmp(&other.transaction.insertion_id()))
+	}
+}
+
+impl<T: VerifiedTransaction, S: cmp::Ord> PartialOrd for ScoreWithRef<T, S> {
+	fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<T: VerifiedTransaction, S: cmp::Ord> PartialEq for ScoreWithRef<T, S> {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == cmp::Ordering::Equal
+	}
+}
+
+impl<T: VerifiedTransaction, S: cmp::Ord> Eq for ScoreWithRef<T, S> {}
+
+/// Set of transactions from a single sender, ordered by nonce.
+#[derive(Debug)]
+struct Transactions<T: VerifiedTransaction, S: Scoring<T>> {
+	transactions: Vec<SharedTransaction<T>>,
+	scores: Vec<S::Score>,
+}
+
+impl<T: VerifiedTransaction, S: Scoring<T>> Default for Transactions<T, S> {
+	fn default() -> Self {
+		Transactions {
+			transactions: Vec::new(),
+			scores: Vec::new(),
+		}
+	}
+}
+
+/// Result of adding a transaction to a sender's set.
+#[derive(Debug)]
+enum AddResult<T> {
+	Ok(T),
+	TooCheap { old: T, new: T },
+	Replaced { old: T, new: T },
+	PushedOut { old: T, new: T },
+}
+
+impl<T: VerifiedTransaction, S: Scoring<T>> Transactions<T, S> {
+	fn is_empty(&self) -> bool {
+		self.transactions.is_empty()
+	}
+
+	/// Transactions of this sender, ordered by nonce.
+	fn as_slice(&self) -> &[SharedTransaction<T>] {
+		&self.transactions
+	}
+
+	/// Front (lowest nonce) and back (highest nonce) transactions with their scores.
+	///
+	/// Returns `(worst, best)` where `best` is the front transaction (the one
+	/// eligible to be mined next) and `worst` is the back one. Both are ranked
+	/// globally against other senders through their score.
+	fn worst_and_best(&self) -> Option<WorstAndBest<T, S::Score>> {
+		let len = self.scores.len();
+		self.scores.first().cloned().map(|best_score| {
+			let worst_score = self.scores[len - 1].clone();
+			let best = self.transactions[0].clone();
+			let worst = self.transactions[len - 1].clone();
+			((worst_score, worst), (best_score, best))
+		})
+	}
+
+	fn update_scores(&mut self, scoring: &S, change: ScoringChange) {
+		scoring.update_scores(&self.transactions, &mut self.scores, change);
+	}
+
+	fn insert(&mut self, index: usize, tx: SharedTransaction<T>, scoring: &S) {
+		self.transactions.insert(index, tx);
+		self.scores.insert(index, Default::default());
+		self.update_scores(scoring, ScoringChange::InsertedAt(index));
+	}
+
+	fn add(&mut self, new: SharedTransaction<T>, scoring: &S, max_count: usize) -> AddResult<SharedTransaction<T>> {
+		match self.transactions.binary_search_by(|old| scoring.compare(old, &new)) {
+			// Conflict: a transaction with the same nonce is already queued.
+			Ok(index) => match scoring.choose(&self.transactions[index], &new) {
+				ScoringChoice::RejectNew => AddResult::TooCheap {
+					old: self.transactions[index].clone(),
+					new,
+				},
+				ScoringChoice::InsertNew => {
+					self.insert(index, new.clone(), scoring);
+					self.enforce_limit(new, max_count)
+				},
+				ScoringChoice::ReplaceOld => {
+					let old = self.transactions[index].clone();
+					self.transactions[index] = new.clone();
+					self.scores[index] = Default::default();
+					self.update_scores(scoring, ScoringChange::ReplacedAt(index));
+					AddResult::Replaced { old, new }
+				},
+			},
+			// Fresh nonce, insert keeping nonce ordering.
+			Err(index) => {
+				self.insert(index, new.clone(), scoring);
+				self.enforce_limit(new, max_count)
+			},
+		}
+	}
+
+	/// Drop the highest-nonce transaction if the per-sender limit is exceeded.
+	fn enforce_limit(&mut self, new: SharedTransaction<T>, max_count: usize) -> AddResult<SharedTransaction<T>> {
+		if self.transactions.len() <= max_count {
+			return AddResult::Ok(new);
+		}
+
+		let old = self.transactions.pop().expect("len > max_count >= 0; qed");
+		self.scores.pop();
+		if old.hash() == new.hash() {
+			AddResult::TooCheap {
+				old: self.transactions.last().cloned().unwrap_or_else(|| new.clone()),
+				new,
+			}
+		} else {
+			AddResult::PushedOut { old, new }
+		}
+	}
+
+	fn remove(&mut self, tx: &T, scoring: &S) -> bool {
+		let index = match self.transactions.binary_search_by(|old| scoring.compare(old, tx)) {
+			Ok(index) => index,
+			Err(_) => {
+				warn!("Attempting to remove non-existent transaction {:?}", tx);
+				return false;
+			},
+		};
+
+		self.transactions.remove(index);
+		self.scores.remove(index);
+		self.update_scores(scoring, ScoringChange::RemovedAt(index));
+		true
+	}
+}
+
+/// A transaction pool ordered by `Scoring` and bounded by `Options`.
+///
+/// The pool is generic over the transaction type `T`, the `Scoring`
+/// implementation `S` and an optional `Listener` `L`.
+#[derive(Debug)]
+pub struct Pool<T: VerifiedTransaction, S: Scoring<T>, L = NoopListener> {
+	listener: L,
+	scoring: S,
+	options: Options<S::Score>,
+	/// Highest `insertion_id` the pool has seen, exposed via [`Pool::insertion_id`]
+	/// so callers can derive the cutoff they pass to [`Pool::cull_stale`].
+	insertion_id: u64,
+	/// Running sum of `VerifiedTransaction::mem_usage` over every stored transaction.
+	mem_usage: usize,
+	transactions: HashMap<T::Sender, Transactions<T, S>>,
+	by_hash: HashMap<T::Hash, SharedTransaction<T>>,
+	best_transactions: BTreeSet<ScoreWithRef<T, S::Score>>,
+	worst_transactions: BTreeSet<ScoreWithRef<T, S::Score>>,
+}
+
+impl<T: VerifiedTransaction, S: Scoring<T> + Default> Default for Pool<T, S> {
+	fn default() -> Self {
+		Self::with_options(Default::default())
+	}
+}
+
+impl<T: VerifiedTransaction, S: Scoring<T> + Default> Pool<T, S> {
+	/// Create a new pool with given options and a default scoring.
+	pub fn with_options(options: Options<S::Score>) -> Self {
+		Self::new(NoopListener, Default::default(), options)
+	}
+}
+
+impl<T, S, L> Pool<T, S, L> where
+	T: VerifiedTransaction,
+	S: Scoring<T>,
+	L: Listener<T>,
+{
+	/// Create a new pool with given listener, scoring and options.
+	pub fn new(listener: L, scoring: S, options: Options<S::Score>) -> Self {
+		Pool {
+			listener,
+			scoring,
+			options,
+			insertion_id: 0,
+			mem_usage: 0,
+			transactions: HashMap::new(),
+			by_hash: HashMap::new(),
+			best_transactions: BTreeSet::new(),
+			worst_transactions: BTreeSet::new(),
+		}
+	}
+
+	/// Attempt to import a transaction into the pool.
+	///
+	/// Returns the shared transaction on success. Evicts the globally worst
+	/// transaction (consulting `replace`) when the pool is full, or rejects the
+	/// newcomer if it cannot beat what is already queued.
+	pub fn import<R: ShouldReplace<T>>(&mut self, transaction: T, replace: &R) -> error::Result<SharedTransaction<T>> {
+		let hash = transaction.hash().clone();
+		let sender = transaction.sender().clone();
+
+		ensure!(
+			!self.by_hash.contains_key(&hash),
+			error::ErrorKind::AlreadyImported(format!("{:?}", hash))
+		);
+
+		let transaction = Arc::new(transaction);
+
+		// Reject transactions that cannot clear the static score floor before
+		// touching any of the data structures.
+		let score = self.transaction_score(&transaction);
+		if score < self.options.minimal_score {
+			self.listener.rejected(&transaction);
+			return Err(error::ErrorKind::TooCheapToEnter(
+				format!("{:?}", hash),
+				format!("{:?}", self.options.minimal_score),
+			).into());
+		}
+
+		// Make room in the pool if necessary.
+		if let Err(err) = self.make_room(&transaction, replace) {
+			self.listener.rejected(&transaction);
+			return Err(err);
+		}
+
+		let (result, prev, current) = {
+			let set = self.transactions.entry(sender.clone()).or_default();
+			let prev = set.worst_and_best();
+			let result = set.add(transaction, &self.scoring, self.options.max_per_sender);
+			(result, prev, set.worst_and_best())
+		};
+		self.update_senders_worst_and_best(prev, current);
+
+		match result {
+			AddResult::Ok(tx) => {
+				self.finalize_insert(&tx, None);
+				self.listener.added(&tx, None);
+				Ok(tx)
+			},
+			AddResult::Replaced { new, old } => {
+				self.finalize_insert(&new, Some(&old));
+				self.listener.added(&new, Some(&old));
+				Ok(new)
+			},
+			AddResult::PushedOut { new, old } => {
+				self.finalize_insert(&new, None);
+				self.finalize_remove(old.hash());
+				self.listener.added(&new, None);
+				self.listener.dropped(&old);
+				Ok(new)
+			},
+			AddResult::TooCheap { new, old } => {
+				let error = error::ErrorKind::TooCheapToReplace(
+					format!("{:?}", old.hash()),
+					format!("{:?}", new.hash()),
+				);
+				self.listener.rejected(&new);
+				// Drop the empty sender entry we may have just created.
+				self.remove_empty_sender(&sender);
+				bail!(error)
+			},
+		}
+	}
+
+	/// Remove a transaction from the pool, marking it invalid or cancelled.
+	pub fn remove(&mut self, hash: &T::Hash, is_invalid: bool) -> Option<SharedTransaction<T>> {
+		if let Some(tx) = self.finalize_remove(hash) {
+			self.remove_from_set(tx.sender(), |set, scoring| { set.remove(&tx, scoring); });
+			if is_invalid {
+				self.listener.invalid(&tx);
+			} else {
+				self.listener.cancelled(&tx);
+			}
+			Some(tx)
+		} else {
+			None
+		}
+	}
+
+	/// Clear the whole pool, notifying the listener about every dropped transaction.
+	pub fn clear(&mut self) {
+		self.transactions.clear();
+		self.best_transactions.clear();
+		self.worst_transactions.clear();
+		self.mem_usage = 0;
+
+		for (_hash, tx) in self.by_hash.drain() {
+			self.listener.dropped(&tx);
+		}
+	}
+
+	/// Drop transactions that have sat in the pool for too long.
+	///
+	/// A transaction is stale when its `insertion_id` predates
+	/// `current_insertion_id - gap`; callers pass the live counter and a gap
+	/// derived from pool capacity (e.g. the id at which half the pool would have
+	/// been replaced). Because dropping a low-nonce transaction strands the
+	/// higher-nonce ones from the same sender, culling removes the whole tail
+	/// from the first stale transaction onwards. `Listener::dropped` fires for
+	/// each removed transaction.
+	pub fn cull_stale(&mut self, current_insertion_id: u64, gap: u64) {
+		let cutoff = current_insertion_id.saturating_sub(gap);
+
+		let to_drop: Vec<SharedTransaction<T>> = self.transactions.values().flat_map(|set| {
+			let txs = set.as_slice();
+			match txs.iter().position(|tx| tx.insertion_id() < cutoff) {
+				Some(first_stale) => txs[first_stale..].to_vec(),
+				None => Vec::new(),
+			}
+		}).collect();
+
+		for tx in to_drop {
+			if self.finalize_remove(tx.hash()).is_some() {
+				self.remove_from_set(tx.sender(), |set, scoring| { set.remove(&tx, scoring); });
+				self.listener.dropped(&tx);
+			}
+		}
+	}
+
+	/// Look up a transaction by its hash.
+	pub fn find(&self, hash: &T::Hash) -> Option<SharedTransaction<T>> {
+		self.by_hash.get(hash).cloned()
+	}
+
+	/// Iterate the transactions eligible for the next block.
+	///
+	/// Transactions are yielded in best-score order across senders, but only
+	/// those the supplied `ready` accepts: for every sender the iterator walks
+	/// nonces in order, emitting while `is_ready` returns `Ready`, abandoning the
+	/// sender on `Future` and skipping on `Stalled`. `Ready::is_ready` is
+	/// stateful, so it is invoked in the exact emission order.
+	pub fn pending<R: Ready<T>>(&self, ready: R) -> PendingIterator<'_, T, R, S, L> {
+		PendingIterator {
+			ready,
+			best_transactions: self.best_transactions.clone(),
+			pool: self,
+		}
+	}
+
+	/// Number of transactions currently in the pool.
+	pub fn light_count(&self) -> usize {
+		self.by_hash.len()
+	}
+
+	/// Highest `insertion_id` among the transactions the pool has inserted so far.
+	///
+	/// Callers derive the stale cutoff from this, passing it (with a gap) to
+	/// [`Pool::cull_stale`] instead of maintaining their own counter.
+	pub fn insertion_id(&self) -> u64 {
+		self.insertion_id
+	}
+
+	/// An authoritative snapshot of the pool's size.
+	///
+	/// Unlike [`Pool::light_status`] this recomputes `mem_usage` by summing over
+	/// the stored transactions rather than trusting the running counter, so it is
+	/// O(n) but independent of the incremental accounting.
+	pub fn status(&self) -> Status {
+		let mem_usage = self.by_hash.values().map(|tx| tx.mem_usage()).sum();
+		Status {
+			transaction_count: self.by_hash.len(),
+			senders: self.transactions.len(),
+			mem_usage,
+		}
+	}
+
+	/// Number of transactions currently queued for a given sender.
+	///
+	/// Lets operators spot a single sender flooding the queue.
+	pub fn count(&self, sender: &T::Sender) -> usize {
+		self.transactions.get(sender).map_or(0, |set| set.as_slice().len())
+	}
+
+	/// Cheap, O(1) counterpart of [`Pool::status`], read straight from the
+	/// running counters without recomputing memory usage.
+	pub fn light_status(&self) -> LightStatus {
+		LightStatus {
+			transaction_count: self.by_hash.len(),
+			senders: self.transactions.len(),
+			mem_usage: self.mem_usage,
+		}
+	}
+
+	/// Access the pool's listener.
+	pub fn listener(&self) -> &L {
+		&self.listener
+	}
+
+	/// The globally worst transaction currently in the pool, if any.
+	///
+	/// Miners surface its score as the "minimum gas price to get in" users have
+	/// to beat once the pool is full.
+	pub fn worst_transaction(&self) -> Option<SharedTransaction<T>> {
+		self.worst_transactions.iter().next_back().map(|worst| worst.transaction.clone())
+	}
+
+	/// Compute the score a single transaction would be assigned.
+	///
+	/// Reuses `Scoring::update_scores` over a one-element slice so the floor
+	/// check stays consistent with the scores stored per sender.
+	fn transaction_score(&self, transaction: &SharedTransaction<T>) -> S::Score {
+		let txs = [transaction.clone()];
+		let mut scores = [Default::default()];
+		self.scoring.update_scores(&txs, &mut scores, ScoringChange::InsertedAt(0));
+		scores[0].clone()
+	}
+
+	fn make_room<R: ShouldReplace<T>>(&mut self, transaction: &SharedTransaction<T>, replace: &R) -> error::Result<()> {
+		while self.by_hash.len() + 1 > self.options.max_count
+			|| self.mem_usage + transaction.mem_usage() > self.options.max_mem_usage
+		{
+			let removed = self.remove_worst(transaction, replace)?;
+			self.listener.dropped(&removed);
+		}
+		Ok(())
+	}
+
+	fn remove_worst<R: ShouldReplace<T>>(&mut self, transaction: &SharedTransaction<T>, replace: &R) -> error::Result<SharedTransaction<T>> {
+		let to_remove = {
+			let worst = match self.worst_transactions.iter().next_back() {
+				None => return Err(error::ErrorKind::TooCheapToEnter(
+					format!("{:?}", transaction.hash()),
+					"unknown".into(),
+				).into()),
+				Some(worst) => worst,
+			};
+
+			let old = ReplaceTransaction::new(
+				&worst.transaction,
+				self.transactions.get(worst.transaction.sender()).map(|t| t.as_slice()),
+			);
+			let new = ReplaceTransaction::new(
+				transaction,
+				self.transactions.get(transaction.sender()).map(|t| t.as_slice()),
+			);
+
+			match replace.should_replace(&old, &new) {
+				// The pool keeps the existing transaction, so the newcomer is too cheap to enter.
+				ScoringChoice::RejectNew => return Err(error::ErrorKind::TooCheapToEnter(
+					format!("{:?}", transaction.hash()),
+					format!("{:?}", worst.score),
+				).into()),
+				ScoringChoice::ReplaceOld | ScoringChoice::InsertNew => worst.transaction.clone(),
+			}
+		};
+
+		self.finalize_remove(to_remove.hash());
+		self.remove_from_set(to_remove.sender(), |set, scoring| { set.remove(&to_remove, scoring); });
+		Ok(to_remove)
+	}
+
+	fn finalize_insert(&mut self, new: &SharedTransaction<T>, old: Option<&SharedTransaction<T>>) {
+		self.insertion_id = cmp::max(self.insertion_id, new.insertion_id());
+		self.mem_usage += new.mem_usage();
+		self.by_hash.insert(new.hash().clone(), new.clone());
+		if let Some(old) = old {
+			self.finalize_remove(old.hash());
+		}
+	}
+
+	fn finalize_remove(&mut self, hash: &T::Hash) -> Option<SharedTransaction<T>> {
+		let removed = self.by_hash.remove(hash);
+		if let Some(ref tx) = removed {
+			self.mem_usage = self.mem_usage.saturating_sub(tx.mem_usage());
+		}
+		removed
+	}
+
+	/// The sender's next transaction by nonce, with its cached score.
+	fn next_in_sender(&self, tx: &SharedTransaction<T>) -> Option<ScoreWithRef<T, S::Score>> {
+		let set = self.transactions.get(tx.sender())?;
+		let index = match set.transactions.binary_search_by(|old| self.scoring.compare(old, tx)) {
+			Ok(index) => index + 1,
+			Err(_) => return None,
+		};
+		set.transactions.get(index).map(|next| ScoreWithRef::new(set.scores[index].clone(), next.clone()))
+	}
+
+	fn remove_empty_sender(&mut self, sender: &T::Sender) {
+		let remove = self.transactions.get(sender).is_some_and(Transactions::is_empty);
+		if remove {
+			self.transactions.remove(sender);
+		}
+	}
+
+	fn remove_from_set<F>(&mut self, sender: &T::Sender, f: F) where
+		F: FnOnce(&mut Transactions<T, S>, &S),
+	{
+		let (prev, current) = if let Some(set) = self.transactions.get_mut(sender) {
+			let prev = set.worst_and_best();
+			f(set, &self.scoring);
+			(prev, set.worst_and_best())
+		} else {
+			return;
+		};
+		self.update_senders_worst_and_best(prev, current);
+		self.remove_empty_sender(&sender.clone());
+	}
+
+	fn update_senders_worst_and_best(
+		&mut self,
+		previous: Option<WorstAndBest<T, S::Score>>,
+		current: Option<WorstAndBest<T, S::Score>>,
+	) {
+		let worst_collection = &mut self.worst_transactions;
+		let best_collection = &mut self.best_transactions;
+
+		let is_same = |a: &Scored<T, S::Score>, b: &Scored<T, S::Score>| {
+			a.0 == b.0 && a.1.hash() == b.1.hash()
+		};
+
+		let update = |collection: &mut BTreeSet<ScoreWithRef<T, S::Score>>, (score, tx): Scored<T, S::Score>, remove: bool| {
+			if remove {
+				collection.remove(&ScoreWithRef::new(score, tx));
+			} else {
+				collection.insert(ScoreWithRef::new(score, tx));
+			}
+		};
+
+		match (previous, current) {
+			(None, Some((worst, best))) => {
+				update(worst_collection, worst, false);
+				update(best_collection, best, false);
+			},
+			(Some((worst, best)), None) => {
+				update(worst_collection, worst, true);
+				update(best_collection, best, true);
+			},
+			(Some((w1, b1)), Some((w2, b2))) => {
+				if !is_same(&w1, &w2) {
+					update(worst_collection, w1, true);
+					update(worst_collection, w2, false);
+				}
+				if !is_same(&b1, &b2) {
+					update(best_collection, b1, true);
+					update(best_collection, b2, false);
+				}
+			},
+			(None, None) => {},
+		}
+	}
+}
+
+/// Iterator over the transactions ready for the next block.
+///
+/// Returned by [`Pool::pending`]. Holds one candidate per sender in best-score
+/// order and re-heaps after every yield so global gas-price priority is kept
+/// across senders as each one is advanced nonce-by-nonce.
+pub struct PendingIterator<'a, T, R, S, L> where
+	T: VerifiedTransaction + 'a,
+	S: Scoring<T> + 'a,
+	L: 'a,
+{
+	ready: R,
+	best_transactions: BTreeSet<ScoreWithRef<T, S::Score>>,
+	pool: &'a Pool<T, S, L>,
+}
+
+impl<'a, T, R, S, L> Iterator for PendingIterator<'a, T, R, S, L> where
+	T: VerifiedTransaction,
+	R: Ready<T>,
+	S: Scoring<T>,
+	L: Listener<T>,
+{
+	type Item = SharedTransaction<T>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let best = match self.best_transactions.iter().next() {
+				Some(best) => best.clone(),
+				None => return None,
+			};
+			self.best_transactions.remove(&best);
+			let tx = best.transaction;
+
+			match self.ready.is_ready(&tx) {
+				Readiness::Ready => {
+					if let Some(next) = self.pool.next_in_sender(&tx) {
+						self.best_transactions.insert(next);
+					}
+					return Some(tx);
+				},
+				// Skip the stalled transaction but keep walking the sender's queue.
+				Readiness::Stalled => {
+					if let Some(next) = self.pool.next_in_sender(&tx) {
+						self.best_transactions.insert(next);
+					}
+				},
+				// A future transaction strands everything behind it, so drop the
+				// rest of this sender's queue by simply not re-queuing it.
+				Readiness::Future => {},
+			}
+		}
+	}
+}
@@ -0,0 +1,116 @@
+//! A ready-made scoring strategy for fee-market mempools.
+//!
+//! `NonceAndGasPrice` orders a single sender's transactions strictly by nonce
+//! and orders senders against each other by gas price, so the crate is usable
+//! out of the box without downstream code reinventing Ethereum ordering.
+
+use std::cmp;
+
+use {
+	ReplaceTransaction, Scoring, ScoringChange, ScoringChoice, SharedTransaction, ShouldReplace,
+	VerifiedTransaction,
+};
+
+/// Everything `NonceAndGasPrice` needs on top of the generic `VerifiedTransaction`.
+///
+/// Kept separate from `VerifiedTransaction` so the pool itself stays agnostic of
+/// nonces and gas prices; only this scoring strategy requires them.
+pub trait ScoredTransaction {
+	/// Transaction nonce, used to order a single sender's transactions.
+	fn nonce(&self) -> u64;
+
+	/// Gas price, used both to decide same-nonce replacements and as the global
+	/// score ordering senders against each other.
+	fn gas_price(&self) -> u64;
+}
+
+/// Default minimal gas price bump required to replace a transaction, in per mille.
+///
+/// `125` per mille is 12.5%, the customary Ethereum replacement bump.
+const DEFAULT_MINIMAL_BUMP_PERMILLE: u64 = 125;
+
+/// Orders transactions by nonce within a sender and by gas price across senders.
+///
+/// Two transactions from the same sender sharing a nonce conflict; the newcomer
+/// only replaces the incumbent if its gas price clears the incumbent's by more
+/// than [`NonceAndGasPrice::minimal_bump_permille`].
+#[derive(Debug, Clone)]
+pub struct NonceAndGasPrice {
+	/// Minimal gas price bump required to replace a same-nonce transaction,
+	/// expressed in per mille (125 == 12.5%).
+	pub minimal_bump_permille: u64,
+}
+
+impl Default for NonceAndGasPrice {
+	fn default() -> Self {
+		NonceAndGasPrice {
+			minimal_bump_permille: DEFAULT_MINIMAL_BUMP_PERMILLE,
+		}
+	}
+}
+
+impl NonceAndGasPrice {
+	/// Returns `true` if `new` beats `old` by more than the configured bump.
+	fn is_sufficient_bump(&self, old: u64, new: u64) -> bool {
+		let threshold = old.saturating_add(old.saturating_mul(self.minimal_bump_permille) / 1000);
+		new > threshold
+	}
+}
+
+impl<T> Scoring<T> for NonceAndGasPrice where T: VerifiedTransaction + ScoredTransaction {
+	type Score = u64;
+
+	fn compare(&self, old: &T, other: &T) -> cmp::Ordering {
+		old.nonce().cmp(&other.nonce())
+	}
+
+	fn choose(&self, old: &T, new: &T) -> ScoringChoice {
+		if self.is_sufficient_bump(old.gas_price(), new.gas_price()) {
+			ScoringChoice::ReplaceOld
+		} else {
+			ScoringChoice::RejectNew
+		}
+	}
+
+	fn update_scores(&self, txs: &[SharedTransaction<T>], scores: &mut [Self::Score], change: ScoringChange) {
+		match change {
+			ScoringChange::InsertedAt(index) | ScoringChange::ReplacedAt(index) => {
+				scores[index] = txs[index].gas_price();
+			},
+			// Scores are shifted with the transactions, so a removal needs no work.
+			ScoringChange::RemovedAt(_) => {},
+		}
+	}
+}
+
+impl<T> ShouldReplace<T> for NonceAndGasPrice where T: VerifiedTransaction + ScoredTransaction {
+	fn should_replace(&self, old: &ReplaceTransaction<T>, new: &ReplaceTransaction<T>) -> ScoringChoice {
+		if old.sender() == new.sender() {
+			// Within a sender keep the earliest transaction; an equal nonce is a
+			// fee replacement decided by the gas price bump.
+			match new.nonce().cmp(&old.nonce()) {
+				cmp::Ordering::Less => ScoringChoice::ReplaceOld,
+				cmp::Ordering::Greater => ScoringChoice::RejectNew,
+				cmp::Ordering::Equal => if self.is_sufficient_bump(old.gas_price(), new.gas_price()) {
+					ScoringChoice::ReplaceOld
+				} else {
+					ScoringChoice::RejectNew
+				},
+			}
+		} else {
+			// Different senders fall back to natural priority ordering: higher gas
+			// price wins, earlier arrivals breaking ties. The stranding invariant
+			// holds structurally rather than here — the pool's global worst is
+			// always a sender's back (highest-nonce) transaction, so evicting it
+			// never strands a higher-nonce sibling. `pooled_by_sender` is
+			// available for policies that need it, but this one does not read it.
+			let old_score = (old.gas_price(), cmp::Reverse(old.insertion_id()));
+			let new_score = (new.gas_price(), cmp::Reverse(new.insertion_id()));
+			if new_score > old_score {
+				ScoringChoice::ReplaceOld
+			} else {
+				ScoringChoice::RejectNew
+			}
+		}
+	}
+}
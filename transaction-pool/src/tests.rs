@@ -0,0 +1,307 @@
+//! Test-only concrete transaction type.
+//!
+//! The pool itself is generic over `VerifiedTransaction`; this Ethereum-shaped
+//! implementation is only compiled for tests so the crate stays agnostic.
+
+use {
+	Listener, NonceAndGasPrice, Options, Pool, Readiness, ScoredTransaction, SharedTransaction,
+	VerifiedTransaction,
+};
+
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Address(pub u64);
+impl From<u64> for Address {
+	fn from(x: u64) -> Self {
+		Address(x)
+	}
+}
+
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct U256(pub u64);
+impl From<u64> for U256 {
+	fn from(x: u64) -> Self {
+		U256(x)
+	}
+}
+
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct H256(pub u64);
+impl From<u64> for H256 {
+	fn from(x: u64) -> Self {
+		H256(x)
+	}
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Transaction {
+	pub hash: H256,
+	pub nonce: U256,
+	pub gas_price: U256,
+	pub gas: U256,
+	pub sender: Address,
+	pub insertion_id: u64,
+	pub mem_usage: usize,
+}
+
+impl VerifiedTransaction for Transaction {
+	type Hash = H256;
+	type Sender = Address;
+
+	fn hash(&self) -> &Self::Hash {
+		&self.hash
+	}
+
+	fn sender(&self) -> &Self::Sender {
+		&self.sender
+	}
+
+	fn mem_usage(&self) -> usize {
+		self.mem_usage
+	}
+
+	fn insertion_id(&self) -> u64 {
+		self.insertion_id
+	}
+}
+
+impl ScoredTransaction for Transaction {
+	fn nonce(&self) -> u64 {
+		self.nonce.0
+	}
+
+	fn gas_price(&self) -> u64 {
+		self.gas_price.0
+	}
+}
+
+/// A listener that counts the callbacks it receives, so tests can assert on
+/// added/dropped/rejected bookkeeping.
+#[derive(Default, Debug)]
+struct CountingListener {
+	added: usize,
+	rejected: usize,
+	dropped: usize,
+	invalid: usize,
+	cancelled: usize,
+}
+
+impl Listener<Transaction> for CountingListener {
+	fn added(&mut self, _tx: &SharedTransaction<Transaction>, _old: Option<&SharedTransaction<Transaction>>) {
+		self.added += 1;
+	}
+	fn rejected(&mut self, _tx: &SharedTransaction<Transaction>) {
+		self.rejected += 1;
+	}
+	fn dropped(&mut self, _tx: &SharedTransaction<Transaction>) {
+		self.dropped += 1;
+	}
+	fn invalid(&mut self, _tx: &SharedTransaction<Transaction>) {
+		self.invalid += 1;
+	}
+	fn cancelled(&mut self, _tx: &SharedTransaction<Transaction>) {
+		self.cancelled += 1;
+	}
+}
+
+/// Build a transaction from its distinguishing fields; `gas` is irrelevant to
+/// ordering so it mirrors `gas_price`.
+fn new_tx(hash: u64, sender: u64, nonce: u64, gas_price: u64, insertion_id: u64, mem_usage: usize) -> Transaction {
+	Transaction {
+		hash: hash.into(),
+		nonce: nonce.into(),
+		gas_price: gas_price.into(),
+		gas: gas_price.into(),
+		sender: sender.into(),
+		insertion_id,
+		mem_usage,
+	}
+}
+
+type TestPool = Pool<Transaction, NonceAndGasPrice, CountingListener>;
+
+fn pool_with(options: Options<u64>) -> TestPool {
+	Pool::new(CountingListener::default(), NonceAndGasPrice::default(), options)
+}
+
+fn pool() -> TestPool {
+	pool_with(Options::default())
+}
+
+#[test]
+fn should_replace_same_nonce_only_above_bump_percentage() {
+	let mut pool = pool();
+	let replace = NonceAndGasPrice::default();
+
+	pool.import(new_tx(1, 1, 0, 1000, 0, 0), &replace).unwrap();
+
+	// 12.5% of 1000 is 125, so 1125 exactly fails to clear the bump.
+	pool.import(new_tx(2, 1, 0, 1125, 1, 0), &replace).unwrap_err();
+	assert_eq!(pool.light_count(), 1);
+	assert!(pool.find(&H256(1)).is_some());
+
+	// One wei over the threshold replaces the incumbent.
+	pool.import(new_tx(3, 1, 0, 1126, 2, 0), &replace).unwrap();
+	assert_eq!(pool.light_count(), 1);
+	assert!(pool.find(&H256(3)).is_some());
+	assert!(pool.find(&H256(1)).is_none());
+}
+
+#[test]
+fn should_keep_distinct_nonces_from_same_sender() {
+	let mut pool = pool();
+	let replace = NonceAndGasPrice::default();
+
+	pool.import(new_tx(1, 1, 0, 100, 0, 0), &replace).unwrap();
+	pool.import(new_tx(2, 1, 1, 100, 1, 0), &replace).unwrap();
+
+	assert_eq!(pool.light_count(), 2);
+}
+
+#[test]
+fn should_reject_transactions_below_minimal_score() {
+	let mut options = Options::default();
+	options.minimal_score = 500;
+	let mut pool = pool_with(options);
+	let replace = NonceAndGasPrice::default();
+
+	pool.import(new_tx(1, 1, 0, 400, 0, 0), &replace).unwrap_err();
+	assert_eq!(pool.light_count(), 0);
+	assert_eq!(pool.listener().rejected, 1);
+
+	pool.import(new_tx(2, 1, 0, 600, 1, 0), &replace).unwrap();
+	assert_eq!(pool.light_count(), 1);
+}
+
+#[test]
+fn should_report_worst_transaction() {
+	let mut pool = pool();
+	let replace = NonceAndGasPrice::default();
+	assert!(pool.worst_transaction().is_none());
+
+	pool.import(new_tx(1, 1, 0, 700, 0, 0), &replace).unwrap();
+	pool.import(new_tx(2, 2, 0, 600, 1, 0), &replace).unwrap();
+
+	let worst = pool.worst_transaction().expect("pool is not empty");
+	assert_eq!(worst.gas_price, U256(600));
+}
+
+#[test]
+fn should_cull_stale_tail_respecting_nonce_invariant() {
+	let mut pool = pool();
+	let replace = NonceAndGasPrice::default();
+	pool.import(new_tx(1, 1, 0, 100, 1, 0), &replace).unwrap();
+	pool.import(new_tx(2, 1, 1, 100, 9, 0), &replace).unwrap();
+
+	// cutoff = 10 - 5 = 5; nonce 0 (id 1) is stale and strands nonce 1 (id 9).
+	pool.cull_stale(10, 5);
+
+	assert_eq!(pool.light_count(), 0);
+	assert_eq!(pool.listener().dropped, 2);
+}
+
+#[test]
+fn should_keep_fresh_front_when_only_tail_is_stale() {
+	let mut pool = pool();
+	let replace = NonceAndGasPrice::default();
+	pool.import(new_tx(1, 1, 0, 100, 9, 0), &replace).unwrap();
+	pool.import(new_tx(2, 1, 1, 100, 1, 0), &replace).unwrap();
+
+	pool.cull_stale(10, 5);
+
+	assert_eq!(pool.light_count(), 1);
+	assert!(pool.find(&H256(1)).is_some());
+	assert!(pool.find(&H256(2)).is_none());
+}
+
+#[test]
+fn pending_emits_best_score_order_across_senders() {
+	let mut pool = pool();
+	let replace = NonceAndGasPrice::default();
+	// Sender 1: nonce 0 @500, nonce 1 @10. Sender 2: nonce 0 @100.
+	pool.import(new_tx(1, 1, 0, 500, 0, 0), &replace).unwrap();
+	pool.import(new_tx(2, 1, 1, 10, 1, 0), &replace).unwrap();
+	pool.import(new_tx(3, 2, 0, 100, 2, 0), &replace).unwrap();
+
+	let ready = |_tx: &Transaction| Readiness::Ready;
+	let order: Vec<u64> = pool.pending(ready).map(|tx| tx.hash.0).collect();
+
+	// 500 (s1n0), then 100 (s2n0), then 10 (s1n1) once sender 1 is advanced.
+	assert_eq!(order, vec![1, 3, 2]);
+}
+
+#[test]
+fn pending_stops_sender_on_future() {
+	let mut pool = pool();
+	let replace = NonceAndGasPrice::default();
+	pool.import(new_tx(1, 1, 0, 500, 0, 0), &replace).unwrap();
+	pool.import(new_tx(2, 1, 1, 400, 1, 0), &replace).unwrap();
+	pool.import(new_tx(3, 2, 0, 100, 2, 0), &replace).unwrap();
+
+	// Nonce >= 1 is not yet ready, so sender 1 is abandoned after its front.
+	let ready = |tx: &Transaction| if tx.nonce.0 >= 1 { Readiness::Future } else { Readiness::Ready };
+	let order: Vec<u64> = pool.pending(ready).map(|tx| tx.hash.0).collect();
+
+	assert_eq!(order, vec![1, 3]);
+}
+
+#[test]
+fn pending_skips_stalled_and_continues_sender() {
+	let mut pool = pool();
+	let replace = NonceAndGasPrice::default();
+	pool.import(new_tx(1, 1, 0, 500, 0, 0), &replace).unwrap();
+	pool.import(new_tx(2, 1, 1, 400, 1, 0), &replace).unwrap();
+
+	// Nonce 0 is already mined (stalled); nonce 1 is the one we want.
+	let ready = |tx: &Transaction| if tx.nonce.0 == 0 { Readiness::Stalled } else { Readiness::Ready };
+	let order: Vec<u64> = pool.pending(ready).map(|tx| tx.hash.0).collect();
+
+	assert_eq!(order, vec![2]);
+}
+
+#[test]
+fn should_track_mem_usage_across_operations() {
+	let mut pool = pool();
+	let replace = NonceAndGasPrice::default();
+
+	pool.import(new_tx(1, 1, 0, 100, 0, 10), &replace).unwrap();
+	pool.import(new_tx(2, 2, 0, 100, 1, 20), &replace).unwrap();
+
+	let status = pool.status();
+	assert_eq!(status.transaction_count, 2);
+	assert_eq!(status.senders, 2);
+	assert_eq!(status.mem_usage, 30);
+	assert_eq!(pool.light_status().mem_usage, 30);
+
+	// Replacing sender 1's transaction adjusts by the delta (30 - 10 + 50).
+	pool.import(new_tx(3, 1, 0, 200, 2, 50), &replace).unwrap();
+	assert_eq!(pool.status().mem_usage, 70);
+	assert_eq!(pool.status().transaction_count, 2);
+
+	// Removing drops the usage back down.
+	pool.remove(&H256(2), false);
+	assert_eq!(pool.status().mem_usage, 50);
+
+	// Culling the last transaction clears everything.
+	pool.cull_stale(100, 0);
+	let status = pool.status();
+	assert_eq!(status.transaction_count, 0);
+	assert_eq!(status.senders, 0);
+	assert_eq!(status.mem_usage, 0);
+}
+
+#[test]
+fn should_enforce_max_mem_usage() {
+	let mut options = Options::default();
+	options.max_mem_usage = 150;
+	let mut pool = pool_with(options);
+	let replace = NonceAndGasPrice::default();
+
+	pool.import(new_tx(1, 1, 0, 100, 0, 100), &replace).unwrap();
+	// Adding the second would reach 200 > 150, so the pricier newcomer evicts the worst.
+	pool.import(new_tx(2, 2, 0, 200, 1, 100), &replace).unwrap();
+
+	assert_eq!(pool.status().transaction_count, 1);
+	assert_eq!(pool.status().mem_usage, 100);
+	assert!(pool.find(&H256(2)).is_some());
+	assert!(pool.find(&H256(1)).is_none());
+}